@@ -1,6 +1,7 @@
-use actix_slog::StructuredLogger;
+use actix_slog::{RequestLogger, StructuredLogger};
 use actix_web::{get, App, HttpRequest, HttpResponse, HttpServer, Responder};
 use chrono::{Local, SecondsFormat};
+use slog::info;
 use slog::o;
 use slog::Drain;
 use slog::{FnValue, PushFnValue};
@@ -14,7 +15,9 @@ pub async fn liveness(_req: HttpRequest) -> impl Responder {
 }
 
 #[get("/")]
-pub async fn index(_req: HttpRequest) -> impl Responder {
+pub async fn index(_req: HttpRequest, RequestLogger(log): RequestLogger) -> impl Responder {
+    // this logger already carries the request's correlation id, uri, etc.
+    info!(log, "handling index request");
     HttpResponse::Ok().body("Hello World")
 }
 
@@ -1,3 +1,7 @@
+/// names of the keys used when logging the various request/response fields
+///
+/// pass a customized instance to [`StructuredLogger::field_names`](crate::StructuredLogger::field_names)
+/// to align the emitted keys with an existing log-ingestion schema.
 pub struct FieldNames {
     pub http_version: &'static str,
     pub http_host: &'static str,
@@ -20,11 +24,11 @@ impl Default for FieldNames {
             http_host: "http_host",
             referer: "referer",
             remote_address: "remote_address",
-            user_agent: "agent",
+            user_agent: "user-agent",
             request_method: "request_method",
-            correlation_id: "correlation-id",
+            correlation_id: "correlation_id",
             uri: "uri",
-            query_string: "query_string",
+            query_string: "query",
             // status: "status",
             // bytes_sent: "bytes_sent",
             // response_time: "response_time",
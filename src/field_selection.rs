@@ -0,0 +1,43 @@
+/// selects which request/response fields get logged, and which additional request headers (by
+/// name) are logged alongside them
+///
+/// pass a customized instance to [`StructuredLogger::with_fields`](crate::StructuredLogger::with_fields)
+/// to avoid leaking noisy or sensitive headers and to reduce log volume on high-traffic services.
+pub struct FieldSelection {
+    pub http_version: bool,
+    pub http_host: bool,
+    pub referer: bool,
+    pub remote_address: bool,
+    pub user_agent: bool,
+    pub request_method: bool,
+    pub correlation_id: bool,
+    pub uri: bool,
+    pub query: bool,
+    pub(crate) extra_headers: Vec<&'static str>,
+}
+
+impl Default for FieldSelection {
+    fn default() -> Self {
+        FieldSelection {
+            http_version: true,
+            http_host: true,
+            referer: true,
+            remote_address: true,
+            user_agent: true,
+            request_method: true,
+            correlation_id: true,
+            uri: true,
+            query: true,
+            extra_headers: Vec::new(),
+        }
+    }
+}
+
+impl FieldSelection {
+    /// additionally log the given request header, using the header name as the log key.
+    pub fn log_header<T: Into<String>>(mut self, name: T) -> Self {
+        self.extra_headers
+            .push(Box::leak(name.into().into_boxed_str()));
+        self
+    }
+}
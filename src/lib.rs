@@ -22,9 +22,10 @@ use actix_web::http::header::{HOST, REFERER, USER_AGENT};
 use actix_web::http::StatusCode;
 use actix_web::web::Bytes;
 use chrono::prelude::*;
-use futures::future::{ok, Ready};
+use futures::future::{err, ok, Ready};
 use pin_project::{pin_project, pinned_drop};
-use slog::{debug, info, o, Logger};
+use regex::RegexSet;
+use slog::{debug, error, info, o, warn, Level, Logger, Record, Serializer, KV};
 use std::borrow::ToOwned;
 use std::collections::HashSet;
 use std::future::Future;
@@ -33,12 +34,43 @@ use std::pin::Pin;
 use std::rc::Rc;
 use std::task::{Context, Poll};
 
+mod field_names;
+mod field_selection;
+mod log_level;
+mod request_logger;
+mod stackdriver;
+mod trace_context;
+
+pub use field_names::FieldNames;
+pub use field_selection::FieldSelection;
+pub use log_level::LevelThresholds;
+pub use request_logger::RequestLogger;
+
+/// a variable-length set of string key-values, used to batch the selected per-request fields
+/// into a single `Logger::new()` call instead of one call per field
+struct RequestFields(Vec<(&'static str, String)>);
+
+impl KV for RequestFields {
+    fn serialize(&self, _record: &Record, serializer: &mut dyn Serializer) -> slog::Result {
+        for (key, value) in &self.0 {
+            serializer.emit_str(key, value)?;
+        }
+        Ok(())
+    }
+}
+
 /// global configuration/builder for the log middleware
 pub struct StructuredLogger(Rc<Inner>);
 
 struct Inner {
     logger: Logger,
     exclude: HashSet<String>,
+    exclude_regex: Vec<String>,
+    field_names: FieldNames,
+    fields: FieldSelection,
+    stackdriver: bool,
+    synthesize_trace_context: bool,
+    level_thresholds: LevelThresholds,
 }
 
 impl StructuredLogger {
@@ -48,6 +80,12 @@ impl StructuredLogger {
         StructuredLogger(Rc::new(Inner {
             logger,
             exclude: HashSet::new(),
+            exclude_regex: Vec::new(),
+            field_names: FieldNames::default(),
+            fields: FieldSelection::default(),
+            stackdriver: false,
+            synthesize_trace_context: true,
+            level_thresholds: LevelThresholds::default(),
         }))
     }
 
@@ -59,6 +97,53 @@ impl StructuredLogger {
             .insert(path.into());
         self
     }
+
+    /// Ignore and do not log access for paths matching the given regular
+    /// expression, e.g. `exclude_regex("^/static/.*")`.
+    pub fn exclude_regex<T: Into<String>>(mut self, pattern: T) -> Self {
+        Rc::get_mut(&mut self.0)
+            .unwrap()
+            .exclude_regex
+            .push(pattern.into());
+        self
+    }
+
+    /// Use custom key names for the logged request/response fields, e.g. to
+    /// align the access-log schema with an existing ingestion pipeline.
+    pub fn field_names(mut self, field_names: FieldNames) -> Self {
+        Rc::get_mut(&mut self.0).unwrap().field_names = field_names;
+        self
+    }
+
+    /// Shape the emitted access record to match Google Cloud Logging's
+    /// `LogEntry`/`HttpRequest` conventions, so it is parsed correctly by the
+    /// Cloud Logging agent without extra transformation.
+    pub fn stackdriver(mut self) -> Self {
+        Rc::get_mut(&mut self.0).unwrap().stackdriver = true;
+        self
+    }
+
+    /// Control whether a synthetic trace-id/span-id is minted when a request carries no (valid)
+    /// `traceparent` header. Enabled by default.
+    pub fn synthesize_trace_context(mut self, enabled: bool) -> Self {
+        Rc::get_mut(&mut self.0).unwrap().synthesize_trace_context = enabled;
+        self
+    }
+
+    /// Configure the status-code thresholds that pick the log level (`info`/`warn`/`error`) of
+    /// the completed-request log entry. Defaults to 4xx => warn, 5xx => error.
+    pub fn level_thresholds(mut self, thresholds: LevelThresholds) -> Self {
+        Rc::get_mut(&mut self.0).unwrap().level_thresholds = thresholds;
+        self
+    }
+
+    /// Select exactly which request/response fields (and, via [`FieldSelection::log_header`],
+    /// which additional request headers) are logged, instead of always emitting the full fixed
+    /// set of fields.
+    pub fn with_fields(mut self, fields: FieldSelection) -> Self {
+        Rc::get_mut(&mut self.0).unwrap().fields = fields;
+        self
+    }
 }
 
 /// "initializer" for the service/the actual middleware (called once per worker)
@@ -75,9 +160,16 @@ where
     type Future = Ready<Result<Self::Transform, Self::InitError>>;
 
     fn new_transform(&self, service: S) -> Self::Future {
+        // compile the regex-set once per worker instead of on every request
+        let regex_set = match RegexSet::new(&self.0.exclude_regex) {
+            Ok(regex_set) => regex_set,
+            Err(_) => return err(()),
+        };
+
         ok(StructuredLoggerMiddleware {
             service,
             inner: self.0.clone(),
+            regex_set,
         })
     }
 }
@@ -85,6 +177,7 @@ where
 /// Logger middleware
 pub struct StructuredLoggerMiddleware<S> {
     inner: Rc<Inner>,
+    regex_set: RegexSet,
 
     /// the next service in the chain, kind of like express' next()
     service: S,
@@ -106,7 +199,8 @@ where
 
     fn call(&mut self, req: ServiceRequest) -> Self::Future {
         // check the exclude-list if to skip this path…
-        let is_exclude = self.inner.exclude.contains(req.path());
+        let is_exclude =
+            self.inner.exclude.contains(req.path()) || self.regex_set.is_match(req.path());
 
         // …but collect other fields nevertheless, to log errors etc.
         let timestamp = Utc::now();
@@ -140,17 +234,71 @@ where
             .and_then(|v| v.to_str().ok())
             .unwrap_or("-");
 
-        let logger = self.inner.logger.new(o!(
-            "http_version" => format!("{:?}", req.version()),
-            "http_host" => host.to_owned(),
-            "referer" => referer.to_owned(),
-            "remote_address" => remote_addr,
-            "user-agent" => user_agent.to_owned(),
-            "request_method" => req.method().to_string(),
-            "correlation_id" => correlation_id.to_owned(),
-            "uri" => req.path().to_owned(),
-            "query" => format!("?{}", req.query_string()),
-        ));
+        let traceparent = req
+            .headers()
+            .get("traceparent")
+            .and_then(|v| v.to_str().ok());
+
+        let (trace_id, span_id, parent_span_id) =
+            match trace_context::resolve(traceparent, self.inner.synthesize_trace_context) {
+                Some(tc) => (tc.trace_id, tc.span_id, tc.parent_span_id.unwrap_or_else(|| "-".to_owned())),
+                None => ("-".to_owned(), "-".to_owned(), "-".to_owned()),
+            };
+
+        let protocol = format!("{:?}", req.version());
+        let request_method = req.method().to_string();
+        let request_url = format!("{}?{}", req.path(), req.query_string());
+
+        let field_names = &self.inner.field_names;
+        let fields = &self.inner.fields;
+
+        let mut kv: Vec<(&'static str, String)> = vec![
+            ("trace_id", trace_id),
+            ("span_id", span_id),
+            ("parent_span_id", parent_span_id),
+        ];
+
+        if fields.http_version {
+            kv.push((field_names.http_version, protocol.clone()));
+        }
+        if fields.http_host {
+            kv.push((field_names.http_host, host.to_owned()));
+        }
+        if fields.referer {
+            kv.push((field_names.referer, referer.to_owned()));
+        }
+        if fields.remote_address {
+            kv.push((field_names.remote_address, remote_addr.clone()));
+        }
+        if fields.user_agent {
+            kv.push((field_names.user_agent, user_agent.to_owned()));
+        }
+        if fields.request_method {
+            kv.push((field_names.request_method, request_method.clone()));
+        }
+        if fields.correlation_id {
+            kv.push((field_names.correlation_id, correlation_id.to_owned()));
+        }
+        if fields.uri {
+            kv.push((field_names.uri, req.path().to_owned()));
+        }
+        if fields.query {
+            kv.push((field_names.query_string, format!("?{}", req.query_string())));
+        }
+        for header_name in &fields.extra_headers {
+            let value = req
+                .headers()
+                .get(*header_name)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or("-")
+                .to_owned();
+            kv.push((*header_name, value));
+        }
+
+        let logger = self.inner.logger.new(RequestFields(kv));
+
+        // make the request-scoped logger available to handlers via the `RequestLogger` extractor
+        req.extensions_mut().insert(logger.clone());
 
         LoggerResponse {
             logger,
@@ -158,6 +306,13 @@ where
             timestamp,
             _t: PhantomData,
             is_exclude,
+            stackdriver: self.inner.stackdriver,
+            protocol,
+            request_method,
+            request_url,
+            user_agent: user_agent.to_owned(),
+            remote_ip: remote_addr,
+            level_thresholds: self.inner.level_thresholds.clone(),
         }
     }
 }
@@ -176,6 +331,14 @@ where
     logger: Logger,
     // if to exclude this request
     is_exclude: bool,
+    // if to shape the final access-log record as a Stackdriver `httpRequest`
+    stackdriver: bool,
+    protocol: String,
+    request_method: String,
+    request_url: String,
+    user_agent: String,
+    remote_ip: String,
+    level_thresholds: LevelThresholds,
     _t: PhantomData<(B,)>,
 }
 
@@ -203,8 +366,24 @@ where
         }
 
         let timestamp = *this.timestamp;
-        let logger = this.logger.new(o!("status" => res.status().as_u16()));
+        let status = res.status().as_u16();
         let is_exclude: bool = *this.is_exclude;
+        let stackdriver = *this.stackdriver;
+        let level = log_level::level_for_status(status, this.level_thresholds);
+
+        let logger = if stackdriver {
+            this.logger
+                .new(o!("status" => status, "severity" => stackdriver::severity(level)))
+        } else {
+            this.logger.new(o!("status" => status))
+        };
+
+        let protocol = this.protocol.clone();
+        let request_method = this.request_method.clone();
+        let request_url = this.request_url.clone();
+        let user_agent = this.user_agent.clone();
+        let remote_ip = this.remote_ip.clone();
+        let level_thresholds = this.level_thresholds.clone();
 
         Poll::Ready(Ok(res.map_body(move |_, body| {
             ResponseBody::Body(StreamLog {
@@ -213,6 +392,14 @@ where
                 body,
                 timestamp,
                 size: 0,
+                stackdriver,
+                status,
+                protocol,
+                request_method,
+                request_url,
+                user_agent,
+                remote_ip,
+                level_thresholds,
             })
         })))
     }
@@ -226,6 +413,14 @@ pub struct StreamLog<B> {
     body: ResponseBody<B>,
     size: usize,
     timestamp: DateTime<Utc>,
+    stackdriver: bool,
+    status: u16,
+    protocol: String,
+    request_method: String,
+    request_url: String,
+    user_agent: String,
+    remote_ip: String,
+    level_thresholds: LevelThresholds,
 }
 
 #[pinned_drop]
@@ -233,8 +428,39 @@ impl<B> PinnedDrop for StreamLog<B> {
     fn drop(self: Pin<&mut Self>) {
         if !self.is_exclude {
             let response_time = Utc::now() - self.timestamp;
-            let response_time = response_time.num_milliseconds();
-            info!(self.logger, "-"; o!("bytes_sent" => self.size), "response_time" => response_time);
+            let level = log_level::level_for_status(self.status, &self.level_thresholds);
+
+            if self.stackdriver {
+                let latency = format!("{:.3}s", response_time.num_milliseconds() as f64 / 1000.0);
+                let http_request = stackdriver::HttpRequest {
+                    request_method: self.request_method.clone(),
+                    request_url: self.request_url.clone(),
+                    status: self.status,
+                    response_size: self.size,
+                    user_agent: self.user_agent.clone(),
+                    remote_ip: self.remote_ip.clone(),
+                    protocol: self.protocol.clone(),
+                    latency,
+                };
+                match level {
+                    Level::Error => error!(self.logger, "-"; o!("httpRequest" => http_request)),
+                    Level::Warning => warn!(self.logger, "-"; o!("httpRequest" => http_request)),
+                    _ => info!(self.logger, "-"; o!("httpRequest" => http_request)),
+                }
+            } else {
+                let response_time = response_time.num_milliseconds();
+                match level {
+                    Level::Error => {
+                        error!(self.logger, "-"; o!("bytes_sent" => self.size), "response_time" => response_time)
+                    }
+                    Level::Warning => {
+                        warn!(self.logger, "-"; o!("bytes_sent" => self.size), "response_time" => response_time)
+                    }
+                    _ => {
+                        info!(self.logger, "-"; o!("bytes_sent" => self.size), "response_time" => response_time)
+                    }
+                }
+            }
         }
     }
 }
@@ -0,0 +1,30 @@
+use slog::Level;
+
+/// status-code thresholds used to pick the `slog::Level` for the completed-request log entry
+///
+/// any status below `warn` logs at `info`, `warn..error` logs at `warn`, and `error` and above
+/// logs at `error`.
+#[derive(Clone)]
+pub struct LevelThresholds {
+    pub warn: u16,
+    pub error: u16,
+}
+
+impl Default for LevelThresholds {
+    fn default() -> Self {
+        LevelThresholds {
+            warn: 400,
+            error: 500,
+        }
+    }
+}
+
+pub(crate) fn level_for_status(status: u16, thresholds: &LevelThresholds) -> Level {
+    if status >= thresholds.error {
+        Level::Error
+    } else if status >= thresholds.warn {
+        Level::Warning
+    } else {
+        Level::Info
+    }
+}
@@ -0,0 +1,35 @@
+use actix_web::dev::Payload;
+use actix_web::{Error, FromRequest, HttpRequest};
+use futures::future::{ok, Ready};
+use slog::{o, Logger};
+
+/// extractor for the request-scoped child `Logger` that [`StructuredLogger`](crate::StructuredLogger)
+/// inserts into the request extensions, already carrying `correlation_id`, `uri` and the other
+/// per-request fields, so handlers can log with the same context as the access log.
+///
+/// ```no_run
+/// use actix_slog::RequestLogger;
+/// use slog::info;
+///
+/// async fn index(RequestLogger(log): RequestLogger) -> &'static str {
+///     info!(log, "handling request");
+///     "Hello World"
+/// }
+/// ```
+pub struct RequestLogger(pub Logger);
+
+impl FromRequest for RequestLogger {
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+    type Config = ();
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let logger = req
+            .extensions()
+            .get::<Logger>()
+            .cloned()
+            .unwrap_or_else(|| Logger::root(slog::Discard, o!()));
+
+        ok(RequestLogger(logger))
+    }
+}
@@ -0,0 +1,55 @@
+use serde::Serialize;
+use slog::Level;
+
+/// shape of the `httpRequest` object expected by Google Cloud Logging's
+/// `LogEntry`/`HttpRequest` conventions
+///
+/// see <https://cloud.google.com/logging/docs/reference/v2/rest/v2/LogEntry#HttpRequest>
+#[derive(Clone, Serialize)]
+pub(crate) struct HttpRequest {
+    #[serde(rename = "requestMethod")]
+    pub request_method: String,
+    #[serde(rename = "requestUrl")]
+    pub request_url: String,
+    pub status: u16,
+    #[serde(rename = "responseSize")]
+    pub response_size: usize,
+    #[serde(rename = "userAgent")]
+    pub user_agent: String,
+    #[serde(rename = "remoteIp")]
+    pub remote_ip: String,
+    pub protocol: String,
+    pub latency: String,
+}
+
+impl slog::Value for HttpRequest {
+    fn serialize(
+        &self,
+        _record: &slog::Record,
+        key: slog::Key,
+        serializer: &mut dyn slog::Serializer,
+    ) -> slog::Result {
+        serializer.emit_serde(key, self)
+    }
+}
+
+impl slog_json::SerdeValue for HttpRequest {
+    fn as_serde(&self) -> &dyn erased_serde::Serialize {
+        self
+    }
+
+    fn to_sendable(&self) -> Box<dyn slog_json::SerdeValue + Send + 'static> {
+        Box::new(self.clone())
+    }
+}
+
+/// map the resolved `slog::Level` (see [`crate::log_level::level_for_status`]) to a Stackdriver
+/// `severity` level, so the two stay in lockstep with whichever `slog` macro `StreamLog::drop`
+/// actually dispatches to.
+pub(crate) fn severity(level: Level) -> &'static str {
+    match level {
+        Level::Error | Level::Critical => "ERROR",
+        Level::Warning => "WARNING",
+        _ => "INFO",
+    }
+}
@@ -0,0 +1,167 @@
+use rand::RngCore;
+
+/// W3C Trace Context identifiers resolved for the current request, either
+/// parsed from an incoming `traceparent` header or freshly generated.
+pub(crate) struct TraceContext {
+    pub trace_id: String,
+    pub span_id: String,
+    pub parent_span_id: Option<String>,
+}
+
+/// resolve the trace context for a request: parse the `traceparent` header
+/// value if present and well-formed, otherwise (when `synthesize` is set)
+/// mint a fresh trace-id; otherwise return `None`. A returned context always
+/// carries a newly minted span-id for this request.
+pub(crate) fn resolve(header: Option<&str>, synthesize: bool) -> Option<TraceContext> {
+    match header.and_then(parse) {
+        Some((trace_id, parent_span_id)) => Some(TraceContext {
+            trace_id,
+            span_id: random_hex(8),
+            parent_span_id: Some(parent_span_id),
+        }),
+        None if synthesize => Some(TraceContext {
+            trace_id: random_hex(16),
+            span_id: random_hex(8),
+            parent_span_id: None,
+        }),
+        None => None,
+    }
+}
+
+/// parse a `traceparent` header of the form `<version>-<trace-id>-<parent-id>-<flags>`,
+/// e.g. `00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01`.
+fn parse(header: &str) -> Option<(String, String)> {
+    let mut parts = header.split('-');
+    let version = parts.next()?;
+    let trace_id = parts.next()?;
+    let parent_id = parts.next()?;
+    let flags = parts.next()?;
+
+    if parts.next().is_some() {
+        return None;
+    }
+
+    if version.len() != 2 || !is_hex(version) {
+        return None;
+    }
+
+    if trace_id.len() != 32 || !is_hex(trace_id) || trace_id.bytes().all(|b| b == b'0') {
+        return None;
+    }
+
+    if parent_id.len() != 16 || !is_hex(parent_id) || parent_id.bytes().all(|b| b == b'0') {
+        return None;
+    }
+
+    if flags.len() != 2 || !is_hex(flags) {
+        return None;
+    }
+
+    Some((trace_id.to_ascii_lowercase(), parent_id.to_ascii_lowercase()))
+}
+
+fn is_hex(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+fn random_hex(bytes: usize) -> String {
+    let mut buf = vec![0u8; bytes];
+    rand::thread_rng().fill_bytes(&mut buf);
+    buf.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_valid_traceparent() {
+        let header = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+        assert_eq!(
+            parse(header),
+            Some((
+                "4bf92f3577b34da6a3ce929d0e0e4736".to_owned(),
+                "00f067aa0ba902b7".to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn lowercases_upper_case_hex() {
+        let header = "00-4BF92F3577B34DA6A3CE929D0E0E4736-00F067AA0BA902B7-01";
+
+        assert_eq!(
+            parse(header),
+            Some((
+                "4bf92f3577b34da6a3ce929d0e0e4736".to_owned(),
+                "00f067aa0ba902b7".to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn rejects_wrong_segment_count() {
+        assert_eq!(parse("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7"), None);
+        assert_eq!(
+            parse("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra"),
+            None
+        );
+    }
+
+    #[test]
+    fn rejects_non_hex_characters() {
+        assert_eq!(
+            parse("00-4bf92f3577b34da6a3ce929d0e0e473g-00f067aa0ba902b7-01"),
+            None
+        );
+        assert_eq!(
+            parse("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902bz-01"),
+            None
+        );
+    }
+
+    #[test]
+    fn rejects_wrong_segment_length() {
+        assert_eq!(parse("00-4bf92f3577b34da6a3ce929d0e0e47-00f067aa0ba902b7-01"), None);
+        assert_eq!(
+            parse("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7ff-01"),
+            None
+        );
+        assert_eq!(
+            parse("000-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"),
+            None
+        );
+    }
+
+    #[test]
+    fn rejects_all_zero_trace_id() {
+        assert_eq!(
+            parse("00-00000000000000000000000000000000-00f067aa0ba902b7-01"),
+            None
+        );
+    }
+
+    #[test]
+    fn rejects_all_zero_parent_id() {
+        assert_eq!(
+            parse("00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01"),
+            None
+        );
+    }
+
+    #[test]
+    fn resolve_falls_back_to_synthetic_trace_on_malformed_header() {
+        let resolved = resolve(Some("not-a-traceparent"), true);
+
+        let context = resolved.expect("synthesize=true should always produce a context");
+        assert_eq!(context.trace_id.len(), 32);
+        assert_eq!(context.span_id.len(), 16);
+        assert!(context.parent_span_id.is_none());
+    }
+
+    #[test]
+    fn resolve_returns_none_when_header_missing_and_synthesize_disabled() {
+        assert!(resolve(None, false).is_none());
+    }
+}